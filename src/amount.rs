@@ -0,0 +1,146 @@
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+use std::str::FromStr;
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// The number of ten-thousandths in one whole unit, i.e. the scale factor for
+/// the four decimal places a monetary amount is tracked to.
+const SCALE: i64 = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+/// A monetary amount with exactly four decimal places, stored as a count of
+/// ten-thousandths rather than a float. This keeps every deposit, withdrawal
+/// and dispute exact, so unlike `f32`, summing many small amounts never
+/// drifts and never needs to be truncated back to four places at output
+/// time.
+pub struct Amount(i64);
+
+impl Amount {
+    pub const ZERO: Amount = Amount(0);
+}
+
+impl Add for Amount {
+    type Output = Amount;
+
+    fn add(self, rhs: Amount) -> Amount {
+        Amount(self.0 + rhs.0)
+    }
+}
+
+impl AddAssign for Amount {
+    fn add_assign(&mut self, rhs: Amount) {
+        self.0 += rhs.0;
+    }
+}
+
+impl Sub for Amount {
+    type Output = Amount;
+
+    fn sub(self, rhs: Amount) -> Amount {
+        Amount(self.0 - rhs.0)
+    }
+}
+
+impl SubAssign for Amount {
+    fn sub_assign(&mut self, rhs: Amount) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl Sum for Amount {
+    fn sum<I: Iterator<Item = Amount>>(iter: I) -> Amount {
+        iter.fold(Amount::ZERO, Add::add)
+    }
+}
+
+impl FromStr for Amount {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (sign, rest) = match s.strip_prefix('-') {
+            Some(rest) => (-1, rest),
+            None => (1, s),
+        };
+
+        let (whole, frac) = match rest.split_once('.') {
+            Some((whole, frac)) => (whole, frac),
+            None => (rest, ""),
+        };
+
+        if whole.is_empty()
+            || frac.len() > 4
+            || !whole.bytes().all(|b| b.is_ascii_digit())
+            || !frac.bytes().all(|b| b.is_ascii_digit())
+        {
+            return Err(format!("invalid amount: {}", s));
+        }
+
+        let whole: i64 = whole.parse().map_err(|_| format!("invalid amount: {}", s))?;
+        let mut frac_digits = frac.to_owned();
+        while frac_digits.len() < 4 {
+            frac_digits.push('0');
+        }
+        let frac: i64 = frac_digits
+            .parse()
+            .map_err(|_| format!("invalid amount: {}", s))?;
+
+        Ok(Amount(sign * (whole * SCALE + frac)))
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let abs = self.0.unsigned_abs();
+        write!(f, "{}{}.{:04}", sign, abs / SCALE as u64, abs % SCALE as u64)
+    }
+}
+
+impl<'de> Deserialize<'de> for Amount {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for Amount {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_display_round_trip() {
+        assert_eq!("1.5".parse::<Amount>().unwrap().to_string(), "1.5000");
+        assert_eq!("0.0001".parse::<Amount>().unwrap().to_string(), "0.0001");
+        assert_eq!("10".parse::<Amount>().unwrap().to_string(), "10.0000");
+        assert_eq!("-2.3".parse::<Amount>().unwrap().to_string(), "-2.3000");
+    }
+
+    #[test]
+    fn test_rejects_more_than_four_decimal_places() {
+        assert!("1.00001".parse::<Amount>().is_err());
+    }
+
+    #[test]
+    fn test_fixed_point_sum_does_not_drift_while_f32_does() {
+        let unit = "0.0001".parse::<Amount>().unwrap();
+        let sum: Amount = std::iter::repeat_n(unit, 100_000).sum();
+        assert_eq!(sum.to_string(), "10.0000");
+
+        let f32_sum: f32 = std::iter::repeat_n(0.0001f32, 100_000).sum();
+        assert_ne!(f32_sum, 10.0);
+    }
+}
@@ -1,23 +1,39 @@
 use std::{collections::HashMap, default::Default};
 
+use crate::amount::Amount;
 use crate::error::Error;
-use crate::transaction::{Transaction, TransactionType};
+use crate::transaction::{AssetId, Transaction, TransactionType};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The lifecycle state of a single transaction, from the balance's point of
+/// view. A transaction starts out `Processed`, can move to `Disputed`, and
+/// from there terminates at either `Resolved` or `ChargedBack`. Every
+/// transition other than these is rejected, which is what stops the same
+/// transaction from being disputed twice or resolved/charged back without
+/// ever having been disputed.
+enum TxState {
+    Processed,
+    Disputed,
+    Resolved,
+    ChargedBack,
+}
 
-#[derive(Debug, PartialEq)]
-/// The state of a client. Note that we don't store available or held funds --
-/// both can be derived from the list of transactions that are in dispute. This
-/// ensures that we always have a single source of truth, instead of requiring
-/// us to update redundant fields in this struct.
-pub struct Client {
-    pub(crate) total: f32,
+#[derive(Debug, Default, PartialEq)]
+/// The state of a single asset balance. Note that we don't store available
+/// or held funds -- both can be derived from the list of transactions that
+/// are in dispute. This ensures that we always have a single source of
+/// truth, instead of requiring us to update redundant fields in this struct.
+pub struct Balance {
+    pub(crate) total: Amount,
     pub(crate) locked: bool,
     disputed_tx: HashMap<u32, Transaction>,
+    tx_states: HashMap<u32, TxState>,
 }
 
-impl Client {
+impl Balance {
     /// Retrieves the amount of funds held by disputes.
     /// Subtract this amount from the total to get the amount of available funds.
-    pub fn get_held(&self) -> f32 {
+    pub fn get_held(&self) -> Amount {
         self.disputed_tx.values().map(|tx| tx.amount.unwrap()).sum()
     }
 
@@ -26,19 +42,19 @@ impl Client {
         self
     }
 
-    fn deposit(&mut self, amount: f32) -> &mut Self {
+    fn deposit(&mut self, amount: Amount) -> &mut Self {
         self.total += amount;
         self
     }
 
-    fn withdraw(&mut self, amount: f32) -> Result<&mut Self, Error> {
+    fn withdraw(&mut self, amount: Amount) -> Result<&mut Self, Error> {
         if self.locked {
             return Err(Error::AccountLocked);
         }
 
         let new_avail = self.total - self.get_held() - amount;
 
-        if new_avail < 0.0 {
+        if new_avail < Amount::ZERO {
             return Err(Error::InsufficientBalance);
         }
 
@@ -47,6 +63,21 @@ impl Client {
     }
 
     fn dispute(&mut self, tx: Transaction) -> Result<&mut Self, Error> {
+        // Only a transaction that has actually completed as `Processed` can
+        // be disputed. `None` must be rejected just like the other states --
+        // it means the referenced transaction never successfully processed
+        // (e.g. a withdrawal that failed with `InsufficientBalance` before
+        // reaching the `Processed` insert), so disputing it would hold funds
+        // that were never actually moved. This also rejects disputing the
+        // same transaction twice and disputing one that has already been
+        // resolved or charged back.
+        match self.tx_states.get(&tx.id) {
+            Some(TxState::Processed) => {}
+            Some(TxState::Disputed | TxState::Resolved | TxState::ChargedBack) | None => {
+                return Err(Error::AlreadyDisputed)
+            }
+        }
+
         // Withdrawal disputes need to be handled differently from deposit disputes,
         // since a reversal of a withdrawal implies _adding_ available funds, not
         // subtracting them. Since we're only disputing now, it would not make sense
@@ -57,11 +88,16 @@ impl Client {
             // which would still make the equation (total = available + held) true.
             self.total += tx.amount.ok_or(Error::AmountMissing)?;
         }
+        self.tx_states.insert(tx.id, TxState::Disputed);
         self.disputed_tx.insert(tx.id, tx);
         Ok(self)
     }
 
     fn resolve(&mut self, tx_id: u32) -> Result<&mut Self, Error> {
+        if !matches!(self.tx_states.get(&tx_id), Some(TxState::Disputed)) {
+            return Err(Error::NotDisputed);
+        }
+
         if let Some(tx) = self.disputed_tx.remove(&tx_id) {
             if matches!(tx.ty, TransactionType::Withdrawal) {
                 // By removing the disputed withdrawal, we decreased the amount
@@ -70,11 +106,16 @@ impl Client {
                 self.total -= tx.amount.ok_or(Error::AmountMissing)?;
             }
         }
+        self.tx_states.insert(tx_id, TxState::Resolved);
 
         Ok(self)
     }
 
     fn chargeback(&mut self, tx_id: u32) -> Result<&mut Self, Error> {
+        if !matches!(self.tx_states.get(&tx_id), Some(TxState::Disputed)) {
+            return Err(Error::NotDisputed);
+        }
+
         if let Some(tx) = self.disputed_tx.remove(&tx_id) {
             match tx.ty {
                 TransactionType::Deposit => {
@@ -89,14 +130,32 @@ impl Client {
                 // Impossible to hit since we should have prevented such kinds of transaction types to be added
                 TransactionType::Dispute
                 | TransactionType::Resolve
-                | TransactionType::Chargeback => unreachable!(),
+                | TransactionType::Chargeback
+                | TransactionType::Transfer => unreachable!(),
             }
             self.locked = true;
         }
+        // A charged-back transaction is terminal: this is what stops a later
+        // dispute from ever re-holding these funds.
+        self.tx_states.insert(tx_id, TxState::ChargedBack);
 
         Ok(self)
     }
+}
+
+#[derive(Debug, Default, PartialEq)]
+/// A client holding a separate [`Balance`] per asset. Deposits, withdrawals,
+/// disputes, resolves and chargebacks are all routed to the asset they
+/// apply to, so a chargeback on one asset never touches another.
+pub struct Client {
+    pub(crate) balances: HashMap<AssetId, Balance>,
+    // Records which asset each processed deposit/withdrawal belongs to, so
+    // that a later resolve/chargeback (which only carries a TxID) can be
+    // routed to the right balance.
+    tx_assets: HashMap<u32, AssetId>,
+}
 
+impl Client {
     /// Processes the given transaction for the client. The 2nd argument is
     /// used only for disputes, and it represents the transaction that is under
     /// dispute.
@@ -110,37 +169,91 @@ impl Client {
         &mut self,
         tx: Transaction,
         disputed_tx: Option<Transaction>,
-    ) -> Result<&mut Self, Error> {
+    ) -> Result<(), Error> {
         match tx.ty {
-            TransactionType::Deposit => Ok(self.deposit(tx.amount.ok_or(Error::AmountMissing)?)),
-            TransactionType::Withdrawal => self.withdraw(tx.amount.ok_or(Error::AmountMissing)?),
+            TransactionType::Deposit => {
+                let asset = tx.asset();
+                let balance = self.balances.entry(asset.clone()).or_default();
+                balance.deposit(tx.amount.ok_or(Error::AmountMissing)?);
+                balance.tx_states.insert(tx.id, TxState::Processed);
+                self.tx_assets.insert(tx.id, asset);
+                Ok(())
+            }
+            TransactionType::Withdrawal => {
+                let asset = tx.asset();
+                let balance = self.balances.entry(asset.clone()).or_default();
+                balance.withdraw(tx.amount.ok_or(Error::AmountMissing)?)?;
+                balance.tx_states.insert(tx.id, TxState::Processed);
+                self.tx_assets.insert(tx.id, asset);
+                Ok(())
+            }
             TransactionType::Dispute => {
                 let disputed_tx = match disputed_tx {
                     Some(t) => t,
-                    None => return Ok(self),
+                    None => return Ok(()),
                 };
 
-                debug_assert_eq!(disputed_tx.client_id, tx.client_id);
+                // `tx_index` in `main.rs` is keyed only by TxID, so a
+                // malformed or malicious input can reference a transaction
+                // that belongs to a different client. Reject that here
+                // rather than letting it either panic (debug builds) or
+                // silently apply someone else's transaction to this
+                // client's balance (release builds).
+                if disputed_tx.client_id != tx.client_id {
+                    return Err(Error::TxClientMismatch);
+                }
                 debug_assert!(matches!(
                     disputed_tx.ty,
                     TransactionType::Deposit | TransactionType::Withdrawal
                 ));
 
-                self.dispute(disputed_tx)
+                let asset = disputed_tx.asset();
+                self.balances.entry(asset).or_default().dispute(disputed_tx)?;
+                Ok(())
             }
             TransactionType::Resolve => self.resolve(tx.id),
             TransactionType::Chargeback => self.chargeback(tx.id),
+            // Transfers move funds between two different `Client`s, which
+            // this method has no access to -- the engine in `main.rs`
+            // intercepts `TransactionType::Transfer` and calls `debit`/
+            // `credit` directly on the two clients involved instead of
+            // routing it through here.
+            TransactionType::Transfer => unreachable!(
+                "transfers must be handled by the caller, not dispatched through process_tx"
+            ),
         }
     }
-}
 
-impl Default for Client {
-    fn default() -> Client {
-        Client {
-            total: 0.0,
-            locked: false,
-            disputed_tx: HashMap::new(),
+    /// Debits `amount` from this client's `asset` balance, as the sending
+    /// side of a transfer. Fails the same way a withdrawal would: the
+    /// account must be unlocked and hold enough available (non-held) funds.
+    pub fn debit(&mut self, asset: AssetId, amount: Amount) -> Result<(), Error> {
+        self.balances.entry(asset).or_default().withdraw(amount)?;
+        Ok(())
+    }
+
+    /// Credits `amount` to this client's `asset` balance, as the receiving
+    /// side of a transfer. Fails with [`Error::AccountLocked`] if the
+    /// recipient's balance for this asset is frozen.
+    pub fn credit(&mut self, asset: AssetId, amount: Amount) -> Result<(), Error> {
+        let balance = self.balances.entry(asset).or_default();
+        if balance.locked {
+            return Err(Error::AccountLocked);
         }
+        balance.deposit(amount);
+        Ok(())
+    }
+
+    fn resolve(&mut self, tx_id: u32) -> Result<(), Error> {
+        let asset = self.tx_assets.get(&tx_id).ok_or(Error::NotDisputed)?;
+        self.balances.entry(asset.clone()).or_default().resolve(tx_id)?;
+        Ok(())
+    }
+
+    fn chargeback(&mut self, tx_id: u32) -> Result<(), Error> {
+        let asset = self.tx_assets.get(&tx_id).ok_or(Error::NotDisputed)?;
+        self.balances.entry(asset.clone()).or_default().chargeback(tx_id)?;
+        Ok(())
     }
 }
 
@@ -150,163 +263,374 @@ mod tests {
     use crate::error::*;
     use crate::transaction::*;
 
+    fn amt(s: &str) -> Amount {
+        s.parse().unwrap()
+    }
+
+    /// Marks `tx_id` as `Processed` on `balance`, mirroring the bookkeeping
+    /// `Client::process_tx` does after a successful deposit/withdrawal. The
+    /// tests below exercise `Balance` directly, below that bookkeeping, so
+    /// they need to set it up by hand before disputing.
+    fn mark_processed(balance: &mut Balance, tx_id: u32) {
+        balance.tx_states.insert(tx_id, TxState::Processed);
+    }
+
     #[test]
     fn test_deposit_and_withdrawal() {
-        let mut client = Client::default();
-        client.deposit(1.0);
+        let mut balance = Balance::default();
+        balance.deposit(amt("1.0"));
 
-        assert!(client.withdraw(0.5).is_ok());
-        assert!(client.withdraw(0.5).is_ok());
-        assert_eq!(client.withdraw(0.5), Err(Error::InsufficientBalance));
+        assert!(balance.withdraw(amt("0.5")).is_ok());
+        assert!(balance.withdraw(amt("0.5")).is_ok());
+        assert_eq!(balance.withdraw(amt("0.5")), Err(Error::InsufficientBalance));
     }
 
     #[test]
     fn test_should_disallow_withdraw_when_avail_funds_is_insufficient() -> Result<(), Error> {
-        let mut client = Client::default();
+        let mut balance = Balance::default();
         let tx = Transaction {
             ty: TransactionType::Deposit,
             client_id: 0,
             id: 1,
-            amount: Some(1.0),
+            amount: Some(amt("1.0")),
+            currency: None,
+            to: None,
         };
 
-        client.process_tx(tx.clone(), None)?.dispute(tx)?;
+        balance.deposit(tx.amount.unwrap());
+        mark_processed(&mut balance, tx.id);
+        balance.dispute(tx)?;
 
-        assert_eq!(client.withdraw(1.0), Err(Error::InsufficientBalance));
-        assert_eq!(client.total, 1.0);
-        assert_eq!(client.get_held(), 1.0);
+        assert_eq!(balance.withdraw(amt("1.0")), Err(Error::InsufficientBalance));
+        assert_eq!(balance.total, amt("1.0"));
+        assert_eq!(balance.get_held(), amt("1.0"));
 
         Ok(())
     }
 
     #[test]
     fn test_should_allow_withdraw_when_dispute_is_resolved() -> Result<(), Error> {
-        let mut client = Client::default();
+        let mut balance = Balance::default();
         let tx = Transaction {
             ty: TransactionType::Deposit,
             client_id: 0,
             id: 1,
-            amount: Some(1.0),
+            amount: Some(amt("1.0")),
+            currency: None,
+            to: None,
         };
 
-        client
-            .process_tx(tx.clone(), None)?
-            .dispute(tx)?
-            .resolve(1)?;
+        balance.deposit(tx.amount.unwrap());
+        mark_processed(&mut balance, tx.id);
+        balance.dispute(tx)?.resolve(1)?;
 
-        assert!(client.withdraw(1.0).is_ok());
-        assert_eq!(client.total, 0.0);
-        assert_eq!(client.get_held(), 0.0);
+        assert!(balance.withdraw(amt("1.0")).is_ok());
+        assert_eq!(balance.total, amt("0.0"));
+        assert_eq!(balance.get_held(), amt("0.0"));
 
         Ok(())
     }
 
     #[test]
     fn test_should_disallow_withdrawal_after_chargeback() -> Result<(), Error> {
-        let mut client = Client::default();
+        let mut balance = Balance::default();
+        let tx = Transaction {
+            ty: TransactionType::Deposit,
+            client_id: 0,
+            id: 1,
+            amount: Some(amt("1.0")),
+            currency: None,
+            to: None,
+        };
+
+        balance.deposit(tx.amount.unwrap()).deposit(amt("2.0"));
+        mark_processed(&mut balance, tx.id);
+        balance.dispute(tx)?.chargeback(1)?;
+
+        assert_eq!(balance.withdraw(amt("1.0")), Err(Error::AccountLocked));
+        assert_eq!(balance.withdraw(amt("5.0")), Err(Error::AccountLocked));
+        assert_eq!(balance.total, amt("2.0"));
+        assert_eq!(balance.get_held(), amt("0.0"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_should_disallow_disputing_the_same_tx_twice() -> Result<(), Error> {
+        let mut balance = Balance::default();
+        let tx = Transaction {
+            ty: TransactionType::Deposit,
+            client_id: 0,
+            id: 1,
+            amount: Some(amt("1.0")),
+            currency: None,
+            to: None,
+        };
+
+        balance.deposit(tx.amount.unwrap());
+        mark_processed(&mut balance, tx.id);
+        balance.dispute(tx.clone())?;
+
+        assert_eq!(balance.dispute(tx), Err(Error::AlreadyDisputed));
+        assert_eq!(balance.total, amt("1.0"));
+        assert_eq!(balance.get_held(), amt("1.0"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_should_disallow_resolving_an_undisputed_tx() -> Result<(), Error> {
+        let mut balance = Balance::default();
+
+        assert_eq!(balance.resolve(1), Err(Error::NotDisputed));
+        assert_eq!(balance.chargeback(1), Err(Error::NotDisputed));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_chargeback_is_terminal() -> Result<(), Error> {
+        let mut balance = Balance::default();
         let tx = Transaction {
             ty: TransactionType::Deposit,
             client_id: 0,
             id: 1,
-            amount: Some(1.0),
+            amount: Some(amt("1.0")),
+            currency: None,
+            to: None,
         };
 
-        client
-            .process_tx(tx.clone(), None)?
-            .deposit(2.0)
-            .dispute(tx)?
-            .chargeback(1)?;
+        balance.deposit(tx.amount.unwrap());
+        mark_processed(&mut balance, tx.id);
+        balance.dispute(tx.clone())?.chargeback(1)?;
 
-        assert_eq!(client.withdraw(1.0), Err(Error::AccountLocked));
-        assert_eq!(client.withdraw(5.0), Err(Error::AccountLocked));
-        assert_eq!(client.total, 2.0);
-        assert_eq!(client.get_held(), 0.0);
+        // A charged-back transaction can never be disputed again, so the
+        // held funds can't be re-hoisted a second time.
+        assert_eq!(balance.dispute(tx), Err(Error::AlreadyDisputed));
+        assert_eq!(balance.resolve(1), Err(Error::NotDisputed));
+        assert_eq!(balance.get_held(), amt("0.0"));
 
         Ok(())
     }
 
     #[test]
     fn test_should_allow_withdrawal_under_dispute_if_avail_funds_exist() -> Result<(), Error> {
-        let mut client = Client::default();
+        let mut balance = Balance::default();
         let tx = Transaction {
             ty: TransactionType::Deposit,
             client_id: 0,
             id: 1,
-            amount: Some(1.0),
+            amount: Some(amt("1.0")),
+            currency: None,
+            to: None,
         };
 
-        client
-            .process_tx(tx.clone(), None)?
-            .deposit(2.0)
-            .dispute(tx)?;
+        balance.deposit(tx.amount.unwrap()).deposit(amt("2.0"));
+        mark_processed(&mut balance, tx.id);
+        balance.dispute(tx)?;
 
-        assert_eq!(client.total, 3.0);
-        assert_eq!(client.get_held(), 1.0);
+        assert_eq!(balance.total, amt("3.0"));
+        assert_eq!(balance.get_held(), amt("1.0"));
 
-        assert!(client.withdraw(2.0).is_ok());
-        assert_eq!(client.total, 1.0);
-        assert_eq!(client.get_held(), 1.0);
+        assert!(balance.withdraw(amt("2.0")).is_ok());
+        assert_eq!(balance.total, amt("1.0"));
+        assert_eq!(balance.get_held(), amt("1.0"));
 
         Ok(())
     }
 
     #[test]
     fn test_dispute_withdrawal() -> Result<(), Error> {
-        let mut client = Client::default();
+        let mut balance = Balance::default();
         let tx = Transaction {
             ty: TransactionType::Withdrawal,
             client_id: 0,
             id: 2,
-            amount: Some(1.0),
+            amount: Some(amt("1.0")),
+            currency: None,
+            to: None,
         };
 
-        client
-            .deposit(3.0)
-            .process_tx(tx.clone(), None)?
-            .dispute(tx)?;
+        balance.deposit(amt("3.0"));
+        balance.withdraw(tx.amount.unwrap())?;
+        mark_processed(&mut balance, tx.id);
+        balance.dispute(tx)?;
 
         let tx2 = Transaction {
             ty: TransactionType::Withdrawal,
             client_id: 0,
             id: 3,
-            amount: Some(2.0),
+            amount: Some(amt("2.0")),
+            currency: None,
+            to: None,
         };
 
-        assert_eq!(client.total, 3.0);
-        assert_eq!(client.get_held(), 1.0);
+        assert_eq!(balance.total, amt("3.0"));
+        assert_eq!(balance.get_held(), amt("1.0"));
 
-        assert!(client.process_tx(tx2.clone(), None).is_ok());
+        assert!(balance.withdraw(tx2.amount.unwrap()).is_ok());
 
-        client.resolve(1)?;
+        // Tx 1 was never processed, let alone disputed, so resolving it is rejected.
+        assert_eq!(balance.resolve(1), Err(Error::NotDisputed));
 
-        assert_eq!(client.total, 1.0);
-        assert_eq!(client.get_held(), 1.0);
+        assert_eq!(balance.total, amt("1.0"));
+        assert_eq!(balance.get_held(), amt("1.0"));
 
-        assert_eq!(client.withdraw(1.0), Err(Error::InsufficientBalance));
+        assert_eq!(balance.withdraw(amt("1.0")), Err(Error::InsufficientBalance));
 
-        client.resolve(2)?;
+        balance.resolve(2)?;
 
-        assert_eq!(client.total, 0.0);
-        assert_eq!(client.get_held(), 0.0);
-        assert_eq!(client.withdraw(2.0), Err(Error::InsufficientBalance));
+        assert_eq!(balance.total, amt("0.0"));
+        assert_eq!(balance.get_held(), amt("0.0"));
+        assert_eq!(balance.withdraw(amt("2.0")), Err(Error::InsufficientBalance));
 
-        client.dispute(tx2)?;
+        mark_processed(&mut balance, tx2.id);
+        balance.dispute(tx2)?;
 
-        assert_eq!(client.total, 2.0);
-        assert_eq!(client.get_held(), 2.0);
-        assert_eq!(client.withdraw(1.0), Err(Error::InsufficientBalance));
+        assert_eq!(balance.total, amt("2.0"));
+        assert_eq!(balance.get_held(), amt("2.0"));
+        assert_eq!(balance.withdraw(amt("1.0")), Err(Error::InsufficientBalance));
 
-        client.chargeback(3)?;
+        balance.chargeback(3)?;
 
-        assert_eq!(client.total, 2.0);
-        assert_eq!(client.get_held(), 0.0);
-        assert_eq!(client.withdraw(1.0), Err(Error::AccountLocked));
+        assert_eq!(balance.total, amt("2.0"));
+        assert_eq!(balance.get_held(), amt("0.0"));
+        assert_eq!(balance.withdraw(amt("1.0")), Err(Error::AccountLocked));
 
-        client.unlock();
+        balance.unlock();
+
+        assert_eq!(balance.total, amt("2.0"));
+        assert_eq!(balance.get_held(), amt("0.0"));
+        assert!(balance.withdraw(amt("2.0")).is_ok());
+
+        Ok(())
+    }
+
+    fn deposit_tx(id: u32, amount: &str, currency: Option<&str>) -> Transaction {
+        Transaction {
+            ty: TransactionType::Deposit,
+            client_id: 0,
+            id,
+            amount: Some(amt(amount)),
+            currency: currency.map(String::from),
+            to: None,
+        }
+    }
+
+    fn dispute_tx(id: u32) -> Transaction {
+        Transaction {
+            ty: TransactionType::Dispute,
+            client_id: 0,
+            id,
+            amount: None,
+            currency: None,
+            to: None,
+        }
+    }
+
+    fn resolve_tx(id: u32) -> Transaction {
+        Transaction {
+            ty: TransactionType::Resolve,
+            client_id: 0,
+            id,
+            amount: None,
+            currency: None,
+            to: None,
+        }
+    }
+
+    fn chargeback_tx(id: u32) -> Transaction {
+        Transaction {
+            ty: TransactionType::Chargeback,
+            client_id: 0,
+            id,
+            amount: None,
+            currency: None,
+            to: None,
+        }
+    }
+
+    #[test]
+    fn test_client_keeps_separate_balances_per_asset() -> Result<(), Error> {
+        let mut client = Client::default();
+        client.process_tx(deposit_tx(1, "10.0", Some("USD")), None)?;
+        client.process_tx(deposit_tx(2, "1.5", Some("BTC")), None)?;
+
+        assert_eq!(client.balances["USD"].total, amt("10.0"));
+        assert_eq!(client.balances["BTC"].total, amt("1.5"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_deposit_without_currency_uses_default_asset() -> Result<(), Error> {
+        let mut client = Client::default();
+        client.process_tx(deposit_tx(1, "5.0", None), None)?;
+
+        assert_eq!(client.balances[DEFAULT_ASSET].total, amt("5.0"));
+        assert_eq!(client.balances.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_chargeback_on_one_asset_does_not_affect_another() -> Result<(), Error> {
+        let mut client = Client::default();
+        let btc_deposit = deposit_tx(1, "1.0", Some("BTC"));
+        client.process_tx(btc_deposit.clone(), None)?;
+        client.process_tx(deposit_tx(2, "100.0", Some("USD")), None)?;
+
+        client.process_tx(dispute_tx(1), Some(btc_deposit))?;
+        client.process_tx(chargeback_tx(1), None)?;
+
+        assert!(client.balances["BTC"].locked);
+        assert!(!client.balances["USD"].locked);
+        assert_eq!(client.balances["USD"].total, amt("100.0"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_routes_to_the_disputed_transactions_asset() -> Result<(), Error> {
+        let mut client = Client::default();
+        let btc_deposit = deposit_tx(1, "1.0", Some("BTC"));
+        client.process_tx(btc_deposit.clone(), None)?;
+        client.process_tx(dispute_tx(1), Some(btc_deposit))?;
+
+        assert_eq!(client.balances["BTC"].get_held(), amt("1.0"));
+
+        client.process_tx(resolve_tx(1), None)?;
+
+        assert_eq!(client.balances["BTC"].get_held(), amt("0.0"));
+        assert_eq!(client.balances["BTC"].total, amt("1.0"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dispute_rejects_a_transaction_belonging_to_another_client() -> Result<(), Error> {
+        let mut client = Client::default();
+        let foreign_deposit = Transaction {
+            ty: TransactionType::Deposit,
+            client_id: 1,
+            id: 1,
+            amount: Some(amt("10.0")),
+            currency: None,
+            to: None,
+        };
+        let dispute = Transaction {
+            ty: TransactionType::Dispute,
+            client_id: 2,
+            id: 1,
+            amount: None,
+            currency: None,
+            to: None,
+        };
 
-        assert_eq!(client.total, 2.0);
-        assert_eq!(client.get_held(), 0.0);
-        assert!(client.withdraw(2.0).is_ok());
+        assert_eq!(
+            client.process_tx(dispute, Some(foreign_deposit)),
+            Err(Error::TxClientMismatch)
+        );
 
         Ok(())
     }
@@ -6,6 +6,20 @@ pub enum Error {
     InsufficientBalance,
     AccountLocked,
     AmountMissing,
+    /// Raised when a dispute references a transaction that is not currently
+    /// in the `Processed` state, e.g. it's already disputed, resolved,
+    /// charged back, or never successfully processed in the first place.
+    AlreadyDisputed,
+    /// Raised when a resolve or chargeback references a transaction that is
+    /// not currently in the `Disputed` state.
+    NotDisputed,
+    /// Raised when a transfer is missing its destination client.
+    MissingDestination,
+    /// Raised when a transfer's amount is not strictly positive.
+    InvalidAmount,
+    /// Raised when a dispute references a transaction that belongs to a
+    /// different client.
+    TxClientMismatch,
 }
 
 impl Display for Error {
@@ -14,9 +28,14 @@ impl Display for Error {
             f,
             "{}",
             match self {
-                Error::InsufficientBalance => "Insufficient balance for withdrawal",
+                Error::InsufficientBalance => "Insufficient available balance",
                 Error::AccountLocked => "Account is frozen",
                 Error::AmountMissing => "No amount specified",
+                Error::AlreadyDisputed => "Transaction is already disputed or finalized",
+                Error::NotDisputed => "Transaction is not currently under dispute",
+                Error::MissingDestination => "Transfer is missing a destination client",
+                Error::InvalidAmount => "Transfer amount must be greater than zero",
+                Error::TxClientMismatch => "Transaction does not belong to the disputing client",
             }
         )
     }
@@ -1,15 +1,19 @@
 use csv::{ReaderBuilder, Writer};
-use serde::{Serialize, Serializer};
+use serde::Serialize;
 use std::{
     collections::HashMap,
+    fs::File,
     io::{self, Write},
     path::{Path, PathBuf},
 };
 use structopt::StructOpt;
 
+use crate::amount::Amount;
 use crate::client::Client;
+use crate::error::Error;
 use crate::transaction::{Transaction, TransactionType};
 
+pub mod amount;
 pub mod client;
 pub mod error;
 pub mod transaction;
@@ -17,7 +21,10 @@ pub mod transaction;
 #[derive(Debug, StructOpt)]
 #[structopt(about = "Payments engine")]
 struct CommandOpt {
-    input: PathBuf,
+    /// Path to the transaction CSV file. Omitted, or passed as `-`, reads
+    /// the transaction stream from stdin instead, for use in a shell
+    /// pipeline.
+    input: Option<PathBuf>,
     /// Only display clients with a locked status
     #[structopt(long)]
     locked: bool,
@@ -25,90 +32,127 @@ struct CommandOpt {
 
 fn main() -> csv::Result<()> {
     let opt = CommandOpt::from_args();
-    // csv::Reader is buffered by default, so that the entire input file
+    // csv::Reader is buffered by default, so that the entire input
     // doesn't get loaded in memory all at once.
+    let source: Box<dyn io::Read> = match opt.input.as_deref() {
+        Some(path) if path != Path::new("-") => Box::new(File::open(path)?),
+        _ => Box::new(io::stdin()),
+    };
     let mut rdr = ReaderBuilder::new()
         // Disputes, resolves and chargebacks may omit the amount column,
         // enable flexible here to allow amount omission.
         .flexible(true)
-        .from_path(opt.input.as_path())?;
+        .from_reader(source);
 
     // Client IDs are only relevant here in this HashMap -- the Client
     // struct itself does not store the ID, thus eliminating redundancy
     // and saving storage space for Clients.
     let mut clients: HashMap<u16, Client> = HashMap::new();
-    // Assumption: TxIDs are monotonically increasing, so we can track which
-    // transactions happened before the other.
-    let mut last_tx_id = 0;
+    // Deposits and withdrawals are the only transactions that can later be
+    // disputed, so we index just those by TxID as they stream past. This
+    // makes a dispute an O(1) lookup instead of re-reading the input
+    // (which also wouldn't be possible in stdin mode, since stdin can't be
+    // rewound), and it no longer matters in what order disputes reference
+    // prior transactions.
+    let mut tx_index: HashMap<u32, Transaction> = HashMap::new();
 
     for res in rdr.deserialize() {
-        process_tx(res?, &mut last_tx_id, &mut clients, opt.input.as_path())?;
+        process_tx(res?, &mut clients, &mut tx_index);
     }
 
     write_client_statements(io::stdout(), clients, opt.locked)
 }
 
-fn process_tx<A: AsRef<Path>>(
+fn process_tx(
     tx: Transaction,
-    last_tx_id: &mut u32,
     clients: &mut HashMap<u16, Client>,
-    txs_path: A,
-) -> csv::Result<()> {
+    tx_index: &mut HashMap<u32, Transaction>,
+) {
     let tx_id = tx.id;
-    let client = clients.entry(tx.client_id).or_default();
 
-    let disputed_tx = if matches!(tx.ty, TransactionType::Dispute) {
-        if tx_id > *last_tx_id {
-            eprintln!(
-                "Error encountered while disputing TxID {}: transaction has not yet happened",
-                tx_id,
-            );
-            return Ok(());
+    if matches!(tx.ty, TransactionType::Transfer) {
+        if let Err(e) = process_transfer(tx, clients) {
+            eprintln!("Error encountered while processing TxID {}: {}", tx_id, e);
         }
+        return;
+    }
 
-        locate_tx(txs_path, tx_id)?
+    let client = clients.entry(tx.client_id).or_default();
+
+    let disputed_tx = if matches!(tx.ty, TransactionType::Dispute) {
+        tx_index.get(&tx_id).cloned()
     } else {
         None
     };
 
-    if matches!(
+    // Only index a deposit/withdrawal once it's actually been processed --
+    // indexing it unconditionally beforehand would let a later dispute
+    // reference a transaction that failed (e.g. a withdrawal that errored
+    // with `InsufficientBalance`), reversing a transfer of funds that never
+    // happened.
+    let indexable_tx = if matches!(
         tx.ty,
         TransactionType::Deposit | TransactionType::Withdrawal
     ) {
-        *last_tx_id += 1;
-
-        debug_assert_eq!(*last_tx_id, tx_id);
-    }
+        Some(tx.clone())
+    } else {
+        None
+    };
 
-    if let Err(e) = client.process_tx(tx, disputed_tx) {
-        eprintln!("Error encountered while processing TxID {}: {}", tx_id, e);
+    match client.process_tx(tx, disputed_tx) {
+        Ok(()) => {
+            if let Some(tx) = indexable_tx {
+                tx_index.insert(tx_id, tx);
+            }
+        }
+        Err(e) => {
+            eprintln!("Error encountered while processing TxID {}: {}", tx_id, e);
+        }
     }
-
-    Ok(())
 }
 
-// Assumption: Disputes rarely happen, so we do not store an entire history of
-// transactions in the client. Instead, whenever there is a dispute, we reopen
-// the list of transactions file and search for the disputed transaction from
-// the beginning.
-fn locate_tx<A: AsRef<Path>>(path: A, tx_id: u32) -> csv::Result<Option<Transaction>> {
-    let mut rdr = ReaderBuilder::new().flexible(true).from_path(path)?;
+/// Moves funds from `tx.client_id` to `tx.to`. This can't go through
+/// `Client::process_tx`, since a transfer touches two different `Client`s
+/// and `process_tx` only ever sees one, so the two `HashMap` entries are
+/// looked up sequentially instead.
+///
+/// The recipient's lock is checked before any funds move, so a transfer
+/// to a frozen account never leaves the sender debited with nothing
+/// credited back. `debit` itself never changes any funds on failure either,
+/// so an insufficient-funds error also leaves both sides' funds untouched
+/// (empty client and balance entries may still be created along the way).
+fn process_transfer(tx: Transaction, clients: &mut HashMap<u16, Client>) -> Result<(), Error> {
+    let to = tx.to.ok_or(Error::MissingDestination)?;
+    let asset = tx.asset();
+    let amount = tx.amount.ok_or(Error::AmountMissing)?;
 
-    for res in rdr.deserialize() {
-        let tx: Transaction = res?;
-
-        // Ensure that we don't dispute a dispute, chargeback or a resolved transaction
-        if tx.id == tx_id
-            && matches!(
-                tx.ty,
-                TransactionType::Deposit | TransactionType::Withdrawal
-            )
-        {
-            return Ok(Some(tx));
-        }
+    // `debit` only guards against running a balance past zero and `credit`
+    // only against a locked balance; neither rejects a negative amount,
+    // which would do the opposite of a transfer: a negative
+    // transfer would increase the sender's total via `withdraw`'s
+    // arithmetic and drive the recipient's total negative via `deposit`,
+    // which has no floor check at all. Reject that case up front.
+    if amount <= Amount::ZERO {
+        return Err(Error::InvalidAmount);
+    }
+
+    if clients
+        .entry(to)
+        .or_default()
+        .balances
+        .get(&asset)
+        .is_some_and(|b| b.locked)
+    {
+        return Err(Error::AccountLocked);
     }
 
-    Ok(None)
+    clients
+        .entry(tx.client_id)
+        .or_default()
+        .debit(asset.clone(), amount)?;
+    clients.entry(to).or_default().credit(asset, amount)?;
+
+    Ok(())
 }
 
 fn write_client_statements<W: Write>(
@@ -116,43 +160,183 @@ fn write_client_statements<W: Write>(
     clients: HashMap<u16, Client>,
     only_locked: bool,
 ) -> csv::Result<()> {
-    fn serialize_amount<S>(data: &f32, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        const PRECISION: i32 = 4;
-        let trunc = (*data * 10.0f32.powi(PRECISION)).trunc() / 10.0f32.powi(PRECISION);
-        serializer.serialize_f32(trunc)
-    }
-
     #[derive(Serialize)]
     struct Row {
         client: u16,
-        #[serde(serialize_with = "serialize_amount")]
-        available: f32,
-        #[serde(serialize_with = "serialize_amount")]
-        held: f32,
-        #[serde(serialize_with = "serialize_amount")]
-        total: f32,
+        asset: String,
+        available: Amount,
+        held: Amount,
+        total: Amount,
         locked: bool,
     }
 
     let mut wtr = Writer::from_writer(output);
 
     for (id, client) in clients {
-        if only_locked && !client.locked {
-            continue;
-        }
+        // A client can hold several asset balances; emit one row per
+        // (client, asset) pair rather than collapsing them together.
+        for (asset, balance) in client.balances {
+            if only_locked && !balance.locked {
+                continue;
+            }
 
-        let held = client.get_held();
-        wtr.serialize(Row {
-            client: id,
-            available: client.total - held,
-            held,
-            total: client.total,
-            locked: client.locked,
-        })?;
+            let held = balance.get_held();
+            wtr.serialize(Row {
+                client: id,
+                asset,
+                available: balance.total - held,
+                held,
+                total: balance.total,
+                locked: balance.locked,
+            })?;
+        }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer_tx(id: u32, from: u16, to: u16, amount: &str) -> Transaction {
+        Transaction {
+            ty: TransactionType::Transfer,
+            client_id: from,
+            id,
+            amount: Some(amount.parse().unwrap()),
+            currency: None,
+            to: Some(to),
+        }
+    }
+
+    fn deposit_tx(id: u32, client_id: u16, amount: &str) -> Transaction {
+        Transaction {
+            ty: TransactionType::Deposit,
+            client_id,
+            id,
+            amount: Some(amount.parse().unwrap()),
+            currency: None,
+            to: None,
+        }
+    }
+
+    fn withdrawal_tx(id: u32, client_id: u16, amount: &str) -> Transaction {
+        Transaction {
+            ty: TransactionType::Withdrawal,
+            client_id,
+            id,
+            amount: Some(amount.parse().unwrap()),
+            currency: None,
+            to: None,
+        }
+    }
+
+    fn dispute_tx(id: u32, client_id: u16) -> Transaction {
+        Transaction {
+            ty: TransactionType::Dispute,
+            client_id,
+            id,
+            amount: None,
+            currency: None,
+            to: None,
+        }
+    }
+
+    #[test]
+    fn test_disputing_a_failed_withdrawal_is_rejected() {
+        let mut clients: HashMap<u16, Client> = HashMap::new();
+        let mut tx_index: HashMap<u32, Transaction> = HashMap::new();
+        process_tx(deposit_tx(1, 1, "10.0"), &mut clients, &mut tx_index);
+
+        // This withdrawal fails with InsufficientBalance and must never be
+        // indexed, since it never actually moved funds.
+        process_tx(withdrawal_tx(2, 1, "500.0"), &mut clients, &mut tx_index);
+
+        // Disputing it anyway must not be allowed to manufacture held funds
+        // out of a transaction that was never processed.
+        process_tx(dispute_tx(2, 1), &mut clients, &mut tx_index);
+
+        assert_eq!(clients[&1].balances["USD"].total, "10.0".parse().unwrap());
+        assert_eq!(clients[&1].balances["USD"].get_held(), Amount::ZERO);
+    }
+
+    #[test]
+    fn test_disputing_another_clients_transaction_is_rejected() {
+        let mut clients: HashMap<u16, Client> = HashMap::new();
+        let mut tx_index: HashMap<u32, Transaction> = HashMap::new();
+        process_tx(deposit_tx(1, 1, "10.0"), &mut clients, &mut tx_index);
+        process_tx(deposit_tx(2, 2, "5.0"), &mut clients, &mut tx_index);
+
+        // Client 2 disputes client 1's deposit by TxID -- must be rejected
+        // rather than panicking or applying it to client 2's balance.
+        process_tx(dispute_tx(1, 2), &mut clients, &mut tx_index);
+
+        assert_eq!(clients[&1].balances["USD"].total, "10.0".parse().unwrap());
+        assert_eq!(clients[&1].balances["USD"].get_held(), Amount::ZERO);
+        assert_eq!(clients[&2].balances["USD"].total, "5.0".parse().unwrap());
+        assert_eq!(clients[&2].balances["USD"].get_held(), Amount::ZERO);
+    }
+
+    #[test]
+    fn test_self_transfer_leaves_balance_unchanged() {
+        let mut clients: HashMap<u16, Client> = HashMap::new();
+        let mut tx_index: HashMap<u32, Transaction> = HashMap::new();
+        process_tx(deposit_tx(1, 1, "10.0"), &mut clients, &mut tx_index);
+
+        process_tx(transfer_tx(2, 1, 1, "4.0"), &mut clients, &mut tx_index);
+
+        assert_eq!(clients[&1].balances["USD"].total, "10.0".parse().unwrap());
+    }
+
+    #[test]
+    fn test_transfer_to_frozen_recipient_is_rejected_without_moving_funds() {
+        let mut clients: HashMap<u16, Client> = HashMap::new();
+        clients.entry(2).or_default().balances.entry("USD".to_string()).or_default().locked = true;
+        let mut tx_index: HashMap<u32, Transaction> = HashMap::new();
+        process_tx(deposit_tx(1, 1, "10.0"), &mut clients, &mut tx_index);
+
+        process_tx(transfer_tx(2, 1, 2, "5.0"), &mut clients, &mut tx_index);
+
+        assert_eq!(clients[&1].balances["USD"].total, "10.0".parse().unwrap());
+        assert_eq!(clients[&2].balances["USD"].total, Amount::ZERO);
+    }
+
+    #[test]
+    fn test_transfer_fails_when_available_funds_are_held_under_dispute() {
+        let mut clients: HashMap<u16, Client> = HashMap::new();
+        let mut tx_index: HashMap<u32, Transaction> = HashMap::new();
+        process_tx(deposit_tx(1, 1, "10.0"), &mut clients, &mut tx_index);
+        process_tx(
+            Transaction {
+                ty: TransactionType::Dispute,
+                client_id: 1,
+                id: 1,
+                amount: None,
+                currency: None,
+                to: None,
+            },
+            &mut clients,
+            &mut tx_index,
+        );
+
+        // All 10.0 is now held, so a transfer of any amount should fail.
+        process_tx(transfer_tx(2, 1, 2, "1.0"), &mut clients, &mut tx_index);
+
+        assert_eq!(clients[&1].balances["USD"].total, "10.0".parse().unwrap());
+        assert!(clients.get(&2).is_none_or(|c| c.balances.is_empty()));
+    }
+
+    #[test]
+    fn test_transfer_with_negative_amount_is_rejected_without_moving_funds() {
+        let mut clients: HashMap<u16, Client> = HashMap::new();
+        let mut tx_index: HashMap<u32, Transaction> = HashMap::new();
+        process_tx(deposit_tx(1, 1, "10.0"), &mut clients, &mut tx_index);
+        process_tx(deposit_tx(2, 2, "5.0"), &mut clients, &mut tx_index);
+
+        process_tx(transfer_tx(3, 1, 2, "-50.0"), &mut clients, &mut tx_index);
+
+        assert_eq!(clients[&1].balances["USD"].total, "10.0".parse().unwrap());
+        assert_eq!(clients[&2].balances["USD"].total, "5.0".parse().unwrap());
+    }
+}
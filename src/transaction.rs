@@ -1,11 +1,22 @@
 use serde::Deserialize;
 
+use crate::amount::Amount;
+
+/// Identifies which asset (e.g. "USD" or "BTC") a transaction or balance
+/// belongs to.
+pub type AssetId = String;
+
+/// The asset a transaction is assumed to apply to when its `currency` column
+/// is absent, so that single-currency inputs keep working unchanged.
+pub(crate) const DEFAULT_ASSET: &str = "USD";
+
 #[derive(Clone, Debug, PartialEq, Deserialize)]
 /// Struct representing a transaction. Primarily used during deserialization.
 ///
-/// All deposit and withdrawal transactions MUST have an amount field.
-/// The amount field for dispute, resolve and chargeback transactions are
-/// OPTIONAL, but if present, the value will be ignored.
+/// All deposit, withdrawal and transfer transactions MUST have an amount
+/// field. The amount field for dispute, resolve and chargeback transactions
+/// are OPTIONAL, but if present, the value will be ignored.
+/// Transfer transactions MUST also have a `to` destination client.
 pub struct Transaction {
     #[serde(rename = "type")]
     pub(crate) ty: TransactionType,
@@ -13,7 +24,23 @@ pub struct Transaction {
     pub(crate) client_id: u16,
     #[serde(rename = "tx")]
     pub(crate) id: u32,
-    pub(crate) amount: Option<f32>,
+    pub(crate) amount: Option<Amount>,
+    /// The asset this transaction applies to. Absent on inputs that predate
+    /// multi-asset support, in which case the engine falls back to a single
+    /// implicit asset.
+    #[serde(default)]
+    pub(crate) currency: Option<AssetId>,
+    /// The recipient client for a `Transfer` transaction. Unused otherwise.
+    #[serde(default)]
+    pub(crate) to: Option<u16>,
+}
+
+impl Transaction {
+    /// The asset this transaction applies to, falling back to
+    /// `DEFAULT_ASSET` for inputs that don't carry a `currency` column.
+    pub(crate) fn asset(&self) -> AssetId {
+        self.currency.clone().unwrap_or_else(|| DEFAULT_ASSET.to_string())
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Deserialize)]
@@ -29,6 +56,8 @@ pub enum TransactionType {
     Resolve,
     #[serde(alias = "chargeback")]
     Chargeback,
+    #[serde(alias = "transfer")]
+    Transfer,
 }
 
 #[cfg(test)]
@@ -51,7 +80,28 @@ deposit,1,1,2.0";
             assert_eq!(tx.ty, TransactionType::Deposit);
             assert_eq!(tx.client_id, 1);
             assert_eq!(tx.id, 1);
-            assert_eq!(tx.amount, Some(2.0));
+            assert_eq!(tx.amount, Some("2.0".parse().unwrap()));
+            assert_eq!(tx.currency, None);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_deposit_with_currency() -> csv::Result<()> {
+        let data = "\
+type,client,tx,amount,currency\n
+deposit,1,1,2.0,BTC";
+
+        let mut rdr = ReaderBuilder::new()
+            .flexible(true)
+            .from_reader(data.as_bytes());
+
+        for res in rdr.deserialize() {
+            let tx: Transaction = res?;
+            assert_eq!(tx.ty, TransactionType::Deposit);
+            assert_eq!(tx.amount, Some("2.0".parse().unwrap()));
+            assert_eq!(tx.currency, Some("BTC".to_string()));
         }
 
         Ok(())
@@ -72,7 +122,8 @@ withdrawal,1,1,2.0";
             assert_eq!(tx.ty, TransactionType::Withdrawal);
             assert_eq!(tx.client_id, 1);
             assert_eq!(tx.id, 1);
-            assert_eq!(tx.amount, Some(2.0));
+            assert_eq!(tx.amount, Some("2.0".parse().unwrap()));
+            assert_eq!(tx.currency, None);
         }
 
         Ok(())
@@ -140,4 +191,26 @@ chargeback,1,1";
 
         Ok(())
     }
+
+    #[test]
+    fn test_deserialize_transfer() -> csv::Result<()> {
+        let data = "\
+type,client,tx,amount,to\n
+transfer,1,1,2.0,2";
+
+        let mut rdr = ReaderBuilder::new()
+            .flexible(true)
+            .from_reader(data.as_bytes());
+
+        for res in rdr.deserialize() {
+            let tx: Transaction = res?;
+            assert_eq!(tx.ty, TransactionType::Transfer);
+            assert_eq!(tx.client_id, 1);
+            assert_eq!(tx.id, 1);
+            assert_eq!(tx.amount, Some("2.0".parse().unwrap()));
+            assert_eq!(tx.to, Some(2));
+        }
+
+        Ok(())
+    }
 }